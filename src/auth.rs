@@ -0,0 +1,245 @@
+use serde::Deserialize;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::time::{Duration, SystemTime};
+
+/// How long we wait on the loopback listener before giving up on the user
+/// finishing the browser flow.
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccessToken {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+    BrowserTimeout,
+    StateMismatch,
+    AuthorizationDenied(String),
+    TokenExchangeFailed(String),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::BrowserTimeout => write!(f, "timed out waiting for the browser login"),
+            AuthError::StateMismatch => write!(f, "OAuth state did not match, rejecting callback"),
+            AuthError::AuthorizationDenied(msg) => write!(f, "login was declined: {}", msg),
+            AuthError::TokenExchangeFailed(msg) => write!(f, "token exchange failed: {}", msg),
+            AuthError::Io(e) => write!(f, "network error during login: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for AuthError {
+    fn from(e: std::io::Error) -> Self {
+        AuthError::Io(e)
+    }
+}
+
+pub struct OAuthConfig {
+    pub authorize_url: String,
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// Runs the full authorization-code flow: opens the provider's authorize
+/// page in the browser, waits for the loopback callback, and exchanges the
+/// code for an access token. Blocks the calling thread, so callers should
+/// run this off the UI thread.
+pub fn login(config: &OAuthConfig) -> Result<AccessToken, AuthError> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    let redirect_uri = format!("http://127.0.0.1:{}/callback", port);
+    let state = random_state();
+
+    let authorize_url = format!(
+        "{}?response_type=code&client_id={}&redirect_uri={}&state={}",
+        config.authorize_url,
+        urlencoding::encode(&config.client_id),
+        urlencoding::encode(&redirect_uri),
+        state
+    );
+
+    if open::that(&authorize_url).is_err() {
+        println!("Could not open a browser automatically. Open this URL to log in: {}", authorize_url);
+    }
+
+    let (code, returned_state) = await_callback(&listener)?;
+    if returned_state != state {
+        return Err(AuthError::StateMismatch);
+    }
+
+    exchange_code(config, &code, &redirect_uri)
+}
+
+fn await_callback(listener: &TcpListener) -> Result<(String, String), AuthError> {
+    listener.set_nonblocking(true)?;
+    let deadline = SystemTime::now() + CALLBACK_TIMEOUT;
+
+    let mut stream = loop {
+        match listener.accept() {
+            Ok((stream, _)) => break stream,
+            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                if SystemTime::now() >= deadline {
+                    return Err(AuthError::BrowserTimeout);
+                }
+                std::thread::sleep(Duration::from_millis(200));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+    stream.set_nonblocking(false)?;
+
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("")
+        .to_string();
+    let query = path.split_once('?').map(|(_, q)| q).unwrap_or("");
+
+    let mut code = None;
+    let mut state = None;
+    let mut error = None;
+    let mut error_description = None;
+    for pair in query.split('&') {
+        let mut parts = pair.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some("code"), Some(v)) => code = Some(urlencoding::decode(v).unwrap_or_default().into_owned()),
+            (Some("state"), Some(v)) => state = Some(urlencoding::decode(v).unwrap_or_default().into_owned()),
+            (Some("error"), Some(v)) => error = Some(urlencoding::decode(v).unwrap_or_default().into_owned()),
+            (Some("error_description"), Some(v)) => {
+                error_description = Some(urlencoding::decode(v).unwrap_or_default().into_owned())
+            }
+            _ => {}
+        }
+    }
+
+    let body = "<html><body>Login complete, you can close this tab.</body></html>";
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+
+    if let Some(error) = error {
+        let detail = error_description.unwrap_or(error);
+        return Err(AuthError::AuthorizationDenied(detail));
+    }
+
+    match (code, state) {
+        (Some(code), Some(state)) => Ok((code, state)),
+        _ => Err(AuthError::StateMismatch),
+    }
+}
+
+fn exchange_code(config: &OAuthConfig, code: &str, redirect_uri: &str) -> Result<AccessToken, AuthError> {
+    let client = reqwest::blocking::Client::new();
+    let response = client
+        .post(&config.token_url)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("client_id", &config.client_id),
+            ("client_secret", &config.client_secret),
+        ])
+        .send()
+        .map_err(|e| AuthError::TokenExchangeFailed(e.to_string()))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().unwrap_or_default();
+        return Err(AuthError::TokenExchangeFailed(format!("{}: {}", status, body)));
+    }
+
+    response
+        .json::<AccessToken>()
+        .map_err(|e| AuthError::TokenExchangeFailed(e.to_string()))
+}
+
+fn random_state() -> String {
+    let bytes: [u8; 16] = rand::random();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Rewrites the `[auth]` section of `callinit.ini` with a freshly acquired
+/// token, preserving every other line in the file.
+pub fn persist_token(ini_path: &str, token: &AccessToken) -> std::io::Result<()> {
+    let mut existing = String::new();
+    if let Ok(mut f) = std::fs::File::open(ini_path) {
+        f.read_to_string(&mut existing)?;
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_auth_section = false;
+    let mut wrote_token = false;
+    let mut wrote_refresh = false;
+    let mut saw_auth_section = false;
+
+    for line in existing.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            if in_auth_section {
+                if !wrote_token {
+                    lines.push(format!("token = {}", token.access_token));
+                }
+                if let Some(ref refresh) = token.refresh_token {
+                    if !wrote_refresh {
+                        lines.push(format!("refresh_token = {}", refresh));
+                    }
+                }
+            }
+            in_auth_section = trimmed == "[auth]";
+            if in_auth_section {
+                saw_auth_section = true;
+            }
+            lines.push(line.to_string());
+            continue;
+        }
+
+        if in_auth_section && trimmed.starts_with("token") {
+            lines.push(format!("token = {}", token.access_token));
+            wrote_token = true;
+            continue;
+        }
+        if in_auth_section && trimmed.starts_with("refresh_token") {
+            if let Some(ref refresh) = token.refresh_token {
+                lines.push(format!("refresh_token = {}", refresh));
+                wrote_refresh = true;
+                continue;
+            }
+        }
+        lines.push(line.to_string());
+    }
+
+    if in_auth_section {
+        if !wrote_token {
+            lines.push(format!("token = {}", token.access_token));
+        }
+        if let Some(ref refresh) = token.refresh_token {
+            if !wrote_refresh {
+                lines.push(format!("refresh_token = {}", refresh));
+            }
+        }
+    } else if !saw_auth_section {
+        lines.push("[auth]".to_string());
+        lines.push(format!("token = {}", token.access_token));
+        if let Some(ref refresh) = token.refresh_token {
+            lines.push(format!("refresh_token = {}", refresh));
+        }
+    }
+
+    std::fs::write(ini_path, lines.join("\n") + "\n")
+}
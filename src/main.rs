@@ -1,13 +1,76 @@
 use eframe::egui;
+use std::net::ToSocketAddrs;
 use std::sync::mpsc;
 use std::thread;
 use arboard::Clipboard;
 
+mod auth;
+mod ntfy;
+mod scheme;
+mod serve;
+mod subscribe;
+use auth::{AccessToken, OAuthConfig};
+use subscribe::ReceivedMessage;
+
 #[macro_use]
 extern crate ini;
 extern crate dirs;
 
 fn main() -> Result<(), eframe::Error> {
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.iter().any(|a| a == "--register-scheme") {
+        if let Err(e) = scheme::register() {
+            eprintln!("callinit --register-scheme failed: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(pos) = args.iter().position(|a| a == "--serve") {
+        let addr = args.get(pos + 1).cloned().unwrap_or_else(|| "127.0.0.1:8787".to_string());
+        let resolved = addr.to_socket_addrs().map(|it| it.collect::<Vec<_>>());
+        match resolved {
+            Ok(addrs) if !addrs.is_empty() && addrs.iter().all(|a| a.ip().is_loopback()) => {}
+            Ok(_) => {
+                eprintln!("callinit --serve only binds to loopback addresses, got: {}", addr);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("callinit --serve could not resolve address {}: {}", addr, e);
+                std::process::exit(1);
+            }
+        }
+
+        let config = MyApp::read_config();
+        if let Err(e) = serve::run(&addr, config.auth_token, config.notify_topic, config.country_code) {
+            eprintln!("callinit --serve failed: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let no_prompt = args.iter().any(|a| a == "--no-prompt");
+    let uri_number = args.iter().skip(1).find_map(|a| scheme::parse_uri(a));
+
+    if no_prompt {
+        let Some(number) = uri_number else {
+            eprintln!("--no-prompt requires a tel:/callinit: URI argument");
+            std::process::exit(1);
+        };
+
+        let config = MyApp::read_config();
+        let formatted = ntfy::format_e164(&number, config.country_code.as_deref());
+        let log = ntfy::build_and_send_logged(&formatted, config.auth_token, config.notify_topic);
+        if log.is_success() {
+            println!("Sent call request for {}", formatted);
+            return Ok(());
+        } else {
+            eprintln!("Failed to send call request: {}", log.error.unwrap_or_default());
+            std::process::exit(1);
+        }
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 200.0])
@@ -18,39 +81,124 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "Call initializer",
         options,
-        Box::new(|_cc| Box::new(MyApp::new())),
+        Box::new(move |_cc| {
+            let mut app = MyApp::new();
+            if let Some(ref number) = uri_number {
+                app.input_text = app.format_e164(number);
+                app.should_focus = true;
+            }
+            Box::new(app)
+        }),
     )
 }
 
+/// Values loaded from `callinit.ini`.
+struct Config {
+    auth_token: Option<String>,
+    country_code: Option<String>,
+    notify_topic: Option<String>,
+    oauth_client_id: Option<String>,
+    oauth_client_secret: Option<String>,
+    oauth_authorize_url: Option<String>,
+    oauth_token_url: Option<String>,
+    ini_path: String,
+}
+
+/// Outcome of a background login attempt, sent back over `auth_receiver`.
+enum AuthResult {
+    Success(AccessToken),
+    Failure(String),
+}
+
 struct MyApp {
     input_text: String,
     should_focus: bool,
-    http_sender: Option<mpsc::Sender<String>>,
-    http_receiver: mpsc::Receiver<String>,
+    http_sender: mpsc::Sender<ntfy::RequestLog>,
+    http_receiver: mpsc::Receiver<ntfy::RequestLog>,
     waiting_for_response: bool,
+    request_history: Vec<ntfy::RequestLog>,
     auth_token: Option<String>,
     country_code: Option<String>,
     notify_topic: Option<String>,
+    ini_path: String,
+    oauth_client_id: Option<String>,
+    oauth_client_secret: Option<String>,
+    oauth_authorize_url: Option<String>,
+    oauth_token_url: Option<String>,
+    auth_sender: mpsc::Sender<AuthResult>,
+    auth_receiver: mpsc::Receiver<AuthResult>,
+    logging_in: bool,
+    login_error: Option<String>,
+    received_receiver: mpsc::Receiver<ReceivedMessage>,
+    received_messages: Vec<ReceivedMessage>,
 }
 
 impl MyApp {
     fn new() -> Self {
         let (tx, rx) = mpsc::channel();
-        let (auth_token, country_code, notify_topic) = Self::read_config();
+        let (auth_tx, auth_rx) = mpsc::channel();
+        let (received_tx, received_rx) = mpsc::channel();
+        let config = Self::read_config();
         let input_text = Self::check_clipboard_for_phone_number();
 
+        if let Some(ref topic) = config.notify_topic {
+            subscribe::spawn(topic.clone(), received_tx);
+        }
+
         Self {
             input_text,
             should_focus: true,
-            http_sender: Some(tx),
+            http_sender: tx,
             http_receiver: rx,
             waiting_for_response: false,
-            auth_token,
-            country_code,
-            notify_topic,
+            request_history: Vec::new(),
+            auth_token: config.auth_token,
+            country_code: config.country_code,
+            notify_topic: config.notify_topic,
+            ini_path: config.ini_path,
+            oauth_client_id: config.oauth_client_id,
+            oauth_client_secret: config.oauth_client_secret,
+            oauth_authorize_url: config.oauth_authorize_url,
+            oauth_token_url: config.oauth_token_url,
+            auth_sender: auth_tx,
+            auth_receiver: auth_rx,
+            logging_in: false,
+            login_error: None,
+            received_receiver: received_rx,
+            received_messages: Vec::new(),
         }
     }
 
+    fn start_login(&mut self) {
+        let (Some(authorize_url), Some(token_url), Some(client_id), Some(client_secret)) = (
+            self.oauth_authorize_url.clone(),
+            self.oauth_token_url.clone(),
+            self.oauth_client_id.clone(),
+            self.oauth_client_secret.clone(),
+        ) else {
+            self.login_error = Some("Missing oauth settings in callinit.ini ([oauth] authorize_url/token_url/client_id/client_secret)".to_string());
+            return;
+        };
+
+        self.logging_in = true;
+        self.login_error = None;
+        let sender = self.auth_sender.clone();
+
+        thread::spawn(move || {
+            let config = OAuthConfig {
+                authorize_url,
+                token_url,
+                client_id,
+                client_secret,
+            };
+            let result = match auth::login(&config) {
+                Ok(token) => AuthResult::Success(token),
+                Err(e) => AuthResult::Failure(e.to_string()),
+            };
+            let _ = sender.send(result);
+        });
+    }
+
     fn check_clipboard_for_phone_number() -> String {
         let Ok(mut clipboard) = Clipboard::new() else {
             return String::new();
@@ -77,112 +225,102 @@ impl MyApp {
         digit_count >= 6 && valid_chars
     }
 
-    fn read_config() -> (Option<String>, Option<String>, Option<String>) {
+    fn read_config() -> Config {
         let filename = dirs::home_dir().unwrap().to_str().unwrap().to_owned() + "/.config/callinit.ini";
         let map = ini!(&filename);
-        let auth_token = map["auth"]["token"].clone();
-        let country_code = map["phone"]["country_code"].clone();
-        let notify_topic = map["notify"]["topic"].clone();
-        (auth_token, country_code, notify_topic)
+        let oauth = |key: &str| map.get("oauth").and_then(|section| section.get(key)).cloned().flatten();
+        Config {
+            auth_token: map["auth"]["token"].clone(),
+            country_code: map["phone"]["country_code"].clone(),
+            notify_topic: map["notify"]["topic"].clone(),
+            oauth_client_id: oauth("client_id"),
+            oauth_client_secret: oauth("client_secret"),
+            oauth_authorize_url: oauth("authorize_url"),
+            oauth_token_url: oauth("token_url"),
+            ini_path: filename,
+        }
     }
 
     fn format_e164(&self, number: &str) -> String {
-        let digits: String = number.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect();
-        if digits.starts_with('+') {
-            digits
-        } else if let Some(ref cc) = self.country_code {
-            if digits.starts_with('0') {
-                format!("+{}{}", cc, &digits[1..])
-            } else {
-                format!("+{}{}", cc, digits)
-            }
-        } else {
-            digits
-        }
+        ntfy::format_e164(number, self.country_code.as_deref())
     }
 
     fn send_http_request(&mut self) {
-        if let Some(sender) = self.http_sender.take() {
-            let text = self.format_e164(&self.input_text);
-            if text.is_empty() {
-                return;
-            }
+        let text = self.format_e164(&self.input_text);
+        self.send_number(text);
+    }
 
-            let auth_token = self.auth_token.clone();
-            let notify_topic = self.notify_topic.clone();
+    /// Sends `number` (already E.164) and records the outcome in
+    /// `request_history` for the inspector panel. Used both for fresh
+    /// sends and for retrying a failed entry.
+    fn send_number(&mut self, number: String) {
+        if self.waiting_for_response || number.is_empty() {
+            return;
+        }
 
-            thread::spawn(move || {
-                let client = reqwest::blocking::Client::new();
-                let mut builder = client
-                    .post("https://ntfy.sh")
-                    .header("Title", format!("Call {}", text));
+        let auth_token = self.auth_token.clone();
+        let notify_topic = self.notify_topic.clone();
+        let sender = self.http_sender.clone();
 
-                if let Some(token) = auth_token {
-                    builder = builder.header("Authorization", format!("Bearer {}", token));
-                    println!("Adding Authorization header with token");
-                } else {
-                    println!("No auth token available, sending request without authentication");
-                }
-		let result = builder
-                    .json(&serde_json::json!({
-                        "topic": notify_topic.unwrap_or_default(),
-                        "message": text,
-                        "actions": [
-                           {
-                              "action": "view",
-                              "label": "Call",
-                              "url": format!("tel:{}", text),
-                              "clear": true
-                           },
-                           {
-                              "action": "view",
-                              "label": "SMS",
-                              "url": format!("sms:{}", text),
-                              "clear": true
-                           },
-                           {
-                              "action": "view",
-                              "label": "WhatsApp",
-                              "url": format!("https://wa.me/{}", text.trim_start_matches('+')),
-                              "clear": true
-                           }
-                        ]
-                     }))
-                    .send();
-
-                match result {
-                    Ok(response) => {
-                        println!("HTTP Response Status: {}", response.status());
-                        if let Ok(body) = response.text() {
-                            println!("Response Body: {}", body);
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("HTTP Request failed: {}", e);
-                    }
-                }
+        thread::spawn(move || {
+            let log = ntfy::build_and_send_logged(&number, auth_token, notify_topic);
+            let _ = sender.send(log);
+        });
 
-                // Signal that the request is complete
-                let _ = sender.send("complete".to_string());
-            });
-            
-            self.waiting_for_response = true;
-        }
+        self.waiting_for_response = true;
     }
 }
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Check if HTTP request completed
-        if let Ok(_) = self.http_receiver.try_recv() {
-            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-            return;
+        if let Ok(log) = self.http_receiver.try_recv() {
+            self.waiting_for_response = false;
+            let success = log.is_success();
+            self.request_history.insert(0, log);
+            if success {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                return;
+            }
+        }
+
+        while let Ok(received) = self.received_receiver.try_recv() {
+            self.received_messages.insert(0, received);
+        }
+
+        if let Ok(result) = self.auth_receiver.try_recv() {
+            self.logging_in = false;
+            match result {
+                AuthResult::Success(token) => {
+                    if let Err(e) = auth::persist_token(&self.ini_path, &token) {
+                        self.login_error = Some(format!("Logged in but failed to save token: {}", e));
+                    } else {
+                        self.login_error = None;
+                    }
+                    self.auth_token = Some(token.access_token);
+                }
+                AuthResult::Failure(e) => {
+                    self.login_error = Some(e);
+                }
+            }
         }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.vertical_centered(|ui| {
                 ui.add_space(50.0);
-                
+
+                if self.auth_token.is_none() {
+                    if self.logging_in {
+                        ui.label("Waiting for browser login...");
+                    } else if ui.button("Log in").clicked() {
+                        self.start_login();
+                    }
+                    if let Some(ref err) = self.login_error {
+                        ui.colored_label(egui::Color32::RED, err);
+                    }
+                    ui.add_space(10.0);
+                }
+
                 ui.label("Enter number to send and press Enter:");
                 ui.add_space(10.0);
 
@@ -199,11 +337,12 @@ impl eframe::App for MyApp {
                 }
 
                 // Handle Enter key press
-                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                    if !self.input_text.is_empty() && !self.waiting_for_response {
-                        println!("Sending HTTP request with text: {}", self.input_text);
-                        self.send_http_request();
-                    }
+                if response.lost_focus()
+                    && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                    && !self.input_text.is_empty()
+                    && !self.waiting_for_response
+                {
+                    self.send_http_request();
                 }
                 if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
                     ctx.send_viewport_cmd(egui::ViewportCommand::Close);
@@ -213,6 +352,69 @@ impl eframe::App for MyApp {
                     ui.add_space(20.0);
                     ui.label("Sending HTTP request...");
                 }
+
+                if !self.received_messages.is_empty() {
+                    ui.add_space(20.0);
+                    ui.separator();
+                    ui.label("Incoming:");
+                    egui::ScrollArea::vertical().max_height(100.0).show(ui, |ui| {
+                        for received in &self.received_messages {
+                            ui.push_id(&received.id, |ui| {
+                                ui.horizontal(|ui| {
+                                    if let Some(ref title) = received.title {
+                                        ui.label(format!("{}: {}", title, received.message));
+                                    } else {
+                                        ui.label(&received.message);
+                                    }
+                                    if ui.button("Call").clicked() {
+                                        let _ = open::that(format!("tel:{}", received.message));
+                                    }
+                                });
+                            });
+                        }
+                    });
+                }
+
+                if !self.request_history.is_empty() {
+                    ui.add_space(20.0);
+                    ui.separator();
+                    let mut retry_number = None;
+                    egui::CollapsingHeader::new(format!("Request history ({})", self.request_history.len()))
+                        .show(ui, |ui| {
+                            egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                                for (i, log) in self.request_history.iter().enumerate() {
+                                    let status = log.status.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string());
+                                    let age_secs = log.timestamp.elapsed().map(|d| d.as_secs()).unwrap_or(0);
+                                    let summary = format!(
+                                        "{} {} auth={} status={} ({}s ago)",
+                                        if log.is_success() { "OK" } else { "FAIL" },
+                                        log.number,
+                                        log.had_auth,
+                                        status,
+                                        age_secs
+                                    );
+                                    egui::CollapsingHeader::new(summary).id_source(i).show(ui, |ui| {
+                                        ui.label(format!("topic: {}", log.topic.clone().unwrap_or_default()));
+                                        ui.label("request:");
+                                        ui.code(&log.request_body);
+                                        if let Some(ref body) = log.response_body {
+                                            ui.label("response:");
+                                            ui.code(body);
+                                        }
+                                        if let Some(ref err) = log.error {
+                                            ui.colored_label(egui::Color32::RED, err);
+                                        }
+                                        if !log.is_success() && ui.button("Retry").clicked() {
+                                            retry_number = Some(log.number.clone());
+                                        }
+                                    });
+                                }
+                            });
+                        });
+                    if let Some(number) = retry_number {
+                        self.send_number(number);
+                    }
+                }
             });
         });
     }
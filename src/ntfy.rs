@@ -0,0 +1,121 @@
+use std::time::SystemTime;
+
+/// Normalizes a user-entered number to E.164, using `country_code` as the
+/// default prefix when the number doesn't already start with `+`.
+pub fn format_e164(number: &str, country_code: Option<&str>) -> String {
+    let digits: String = number.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect();
+    if digits.starts_with('+') {
+        digits
+    } else if let Some(cc) = country_code {
+        if let Some(rest) = digits.strip_prefix('0') {
+            format!("+{}{}", cc, rest)
+        } else {
+            format!("+{}{}", cc, digits)
+        }
+    } else {
+        digits
+    }
+}
+
+/// A single outbound ntfy POST, captured for the in-app request inspector.
+#[derive(Debug, Clone)]
+pub struct RequestLog {
+    pub timestamp: SystemTime,
+    pub number: String,
+    pub topic: Option<String>,
+    pub had_auth: bool,
+    pub request_body: String,
+    pub status: Option<u16>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+}
+
+impl RequestLog {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none() && self.status.is_some_and(|s| (200..300).contains(&s))
+    }
+}
+
+fn build_payload(number: &str, notify_topic: &Option<String>) -> serde_json::Value {
+    serde_json::json!({
+        "topic": notify_topic.clone().unwrap_or_default(),
+        "message": number,
+        "actions": [
+           {
+              "action": "view",
+              "label": "Call",
+              "url": format!("tel:{}", number),
+              "clear": true
+           },
+           {
+              "action": "view",
+              "label": "SMS",
+              "url": format!("sms:{}", number),
+              "clear": true
+           },
+           {
+              "action": "view",
+              "label": "WhatsApp",
+              "url": format!("https://wa.me/{}", number.trim_start_matches('+')),
+              "clear": true
+           }
+        ]
+    })
+}
+
+/// Posts a "call me" ntfy notification for `number` and captures the full
+/// request/response for the inspector panel.
+pub fn build_and_send_logged(number: &str, auth_token: Option<String>, notify_topic: Option<String>) -> RequestLog {
+    let payload = build_payload(number, &notify_topic);
+    let had_auth = auth_token.is_some();
+
+    let client = reqwest::blocking::Client::new();
+    let mut builder = client
+        .post("https://ntfy.sh")
+        .header("Title", format!("Call {}", number));
+
+    if let Some(ref token) = auth_token {
+        builder = builder.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let mut log = RequestLog {
+        timestamp: SystemTime::now(),
+        number: number.to_string(),
+        topic: notify_topic,
+        had_auth,
+        request_body: payload.to_string(),
+        status: None,
+        response_body: None,
+        error: None,
+    };
+
+    match builder.json(&payload).send() {
+        Ok(response) => {
+            log.status = Some(response.status().as_u16());
+            match response.text() {
+                Ok(body) => log.response_body = Some(body),
+                Err(e) => log.error = Some(e.to_string()),
+            }
+        }
+        Err(e) => log.error = Some(format!("HTTP request failed: {}", e)),
+    }
+
+    log
+}
+
+/// Posts a "call me" ntfy notification for `number` and returns the ntfy
+/// response body, or an error message if the request didn't succeed.
+pub fn build_and_send(number: &str, auth_token: Option<String>, notify_topic: Option<String>) -> Result<String, String> {
+    let log = build_and_send_logged(number, auth_token, notify_topic);
+    if log.is_success() {
+        Ok(log.response_body.unwrap_or_default())
+    } else {
+        Err(log.error.unwrap_or_else(|| {
+            format!(
+                "ntfy request failed with {}: {}",
+                log.status.map(|s| s.to_string()).unwrap_or_else(|| "no response".to_string()),
+                log.response_body.unwrap_or_default()
+            )
+        }))
+    }
+}
@@ -0,0 +1,91 @@
+/// Registers this binary as the OS handler for `tel:` and `callinit:`
+/// URLs, so clicking a phone link in a browser (or another app) launches
+/// callinit with the number on the command line. Linux and Windows are
+/// registered; on macOS nothing is registered and a message explains the
+/// Info.plist entry an app bundle needs instead.
+pub fn register() -> std::io::Result<()> {
+    #[cfg(target_os = "linux")]
+    return register_linux();
+
+    #[cfg(target_os = "macos")]
+    return register_macos();
+
+    #[cfg(target_os = "windows")]
+    return register_windows();
+
+    #[allow(unreachable_code)]
+    {
+        eprintln!("--register-scheme is not supported on this platform yet");
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn register_linux() -> std::io::Result<()> {
+    let exe = std::env::current_exe()?;
+    let exe = exe.to_string_lossy();
+
+    let applications_dir = dirs::data_dir().unwrap().join("applications");
+    std::fs::create_dir_all(&applications_dir)?;
+    let desktop_file = applications_dir.join("callinit.desktop");
+
+    let contents = format!(
+        "[Desktop Entry]\nType=Application\nName=Call initializer\nExec={} %u\nMimeType=x-scheme-handler/tel;x-scheme-handler/callinit;\nNoDisplay=true\nTerminal=false\n",
+        exe
+    );
+    std::fs::write(&desktop_file, contents)?;
+
+    let _ = std::process::Command::new("xdg-mime")
+        .args(["default", "callinit.desktop", "x-scheme-handler/tel"])
+        .status();
+    let _ = std::process::Command::new("xdg-mime")
+        .args(["default", "callinit.desktop", "x-scheme-handler/callinit"])
+        .status();
+    let _ = std::process::Command::new("update-desktop-database")
+        .arg(&applications_dir)
+        .status();
+
+    println!("Registered {} as the tel:/callinit: handler", desktop_file.display());
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn register_macos() -> std::io::Result<()> {
+    eprintln!(
+        "macOS scheme registration requires a CFBundleURLTypes entry (with tel/callinit in \
+         CFBundleURLSchemes) in the app bundle's Info.plist; this can't be added to a bare \
+         binary at runtime. Package callinit as a .app bundle with that entry instead."
+    );
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn register_windows() -> std::io::Result<()> {
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    let exe = std::env::current_exe()?.to_string_lossy().to_string();
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+
+    for scheme in ["tel", "callinit"] {
+        let (key, _) = hkcu.create_subkey(format!("Software\\Classes\\{}", scheme))?;
+        key.set_value("", &"URL:Call initializer protocol")?;
+        key.set_value("URL Protocol", &"")?;
+        let (command_key, _) = key.create_subkey("shell\\open\\command")?;
+        command_key.set_value("", &format!("\"{}\" \"%1\"", exe))?;
+    }
+
+    println!("Registered tel:/callinit: handlers in HKCU\\Software\\Classes");
+    Ok(())
+}
+
+/// Extracts the phone number from a `tel:` or `callinit:` URI passed on
+/// the command line, e.g. by a browser handing off a clicked phone link.
+pub fn parse_uri(arg: &str) -> Option<String> {
+    for scheme in ["tel:", "callinit://", "callinit:"] {
+        if let Some(rest) = arg.strip_prefix(scheme) {
+            return Some(rest.trim_start_matches('/').to_string());
+        }
+    }
+    None
+}
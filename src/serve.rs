@@ -0,0 +1,101 @@
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use crate::ntfy;
+
+#[derive(Deserialize)]
+struct CallRequest {
+    number: String,
+}
+
+/// Runs the headless `--serve` mode: listens on `addr` and turns
+/// `POST /call` requests into ntfy "call me" notifications, without ever
+/// opening the egui window.
+pub fn run(addr: &str, auth_token: Option<String>, notify_topic: Option<String>, country_code: Option<String>) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    println!("callinit serving on http://{}", addr);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let auth_token = auth_token.clone();
+        let notify_topic = notify_topic.clone();
+        let country_code = country_code.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, auth_token, notify_topic, country_code) {
+                eprintln!("callinit serve: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    auth_token: Option<String>,
+    notify_topic: Option<String>,
+    country_code: Option<String>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts.next().unwrap_or("").to_string();
+
+    let mut content_length = 0usize;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some(value) = header
+            .split_once(':')
+            .filter(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+            .map(|(_, value)| value)
+        {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body)?;
+
+    if method != "POST" || path != "/call" {
+        return write_response(&mut stream, 400, "unsupported route, expected POST /call");
+    }
+
+    let call_request: CallRequest = match serde_json::from_slice(&body) {
+        Ok(req) => req,
+        Err(_) => return write_response(&mut stream, 400, "invalid JSON body, expected {\"number\": \"...\"}"),
+    };
+
+    let number = ntfy::format_e164(&call_request.number, country_code.as_deref());
+    if number.is_empty() {
+        return write_response(&mut stream, 400, "number did not contain any digits");
+    }
+
+    match ntfy::build_and_send(&number, auth_token, notify_topic) {
+        Ok(body) => write_response(&mut stream, 200, &body),
+        Err(e) => write_response(&mut stream, 500, &e),
+    }
+}
+
+fn write_response(stream: &mut TcpStream, status: u16, body: &str) -> std::io::Result<()> {
+    let status_text = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        _ => "Internal Server Error",
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+        status,
+        status_text,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes())
+}
@@ -0,0 +1,93 @@
+use serde::Deserialize;
+use std::io::{BufRead, BufReader};
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+/// One line of ntfy's `/json` streaming endpoint.
+#[derive(Debug, Clone, Deserialize)]
+struct NtfyEvent {
+    id: String,
+    event: String,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+}
+
+/// A `message`-type event received from the subscribed topic.
+#[derive(Debug, Clone)]
+pub struct ReceivedMessage {
+    pub id: String,
+    pub title: Option<String>,
+    pub message: String,
+}
+
+/// Spawns a background thread that subscribes to `topic`'s ntfy JSON
+/// stream and forwards `message` events over `sender`. Reconnects with
+/// backoff if the stream drops, tracking the last event id so a
+/// reconnect resumes with `since=<id>` instead of replaying old events.
+pub fn spawn(topic: String, sender: Sender<ReceivedMessage>) {
+    std::thread::spawn(move || {
+        let mut since = "all".to_string();
+        let mut backoff = Duration::from_secs(1);
+
+        loop {
+            match stream_once(&topic, &since, &sender) {
+                Ok(Some(last_id)) => {
+                    since = last_id;
+                    backoff = Duration::from_secs(1);
+                }
+                Ok(None) => {
+                    backoff = Duration::from_secs(1);
+                }
+                Err(e) => {
+                    eprintln!("ntfy subscribe error: {}", e);
+                }
+            }
+            std::thread::sleep(backoff);
+            backoff = std::cmp::min(backoff * 2, Duration::from_secs(60));
+        }
+    });
+}
+
+/// Opens the streaming GET and forwards events until the connection
+/// drops, returning the id of the last event seen (if any). ntfy's
+/// `since=<id>` form resumes strictly after that event, unlike
+/// `since=<unix_ts>` which would replay it on every reconnect.
+fn stream_once(topic: &str, since: &str, sender: &Sender<ReceivedMessage>) -> Result<Option<String>, String> {
+    let url = format!("https://ntfy.sh/{}/json?since={}", topic, since);
+    let client = reqwest::blocking::Client::new();
+    let response = client.get(&url).send().map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("unexpected status {}", response.status()));
+    }
+
+    let mut last_id = None;
+    for line in BufReader::new(response).lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event: NtfyEvent = match serde_json::from_str(&line) {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+
+        last_id = Some(event.id.clone());
+
+        if event.event == "keepalive" || event.event == "open" {
+            continue;
+        }
+
+        if let Some(message) = event.message {
+            let _ = sender.send(ReceivedMessage {
+                id: event.id,
+                title: event.title,
+                message,
+            });
+        }
+    }
+
+    Ok(last_id)
+}